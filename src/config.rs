@@ -0,0 +1,54 @@
+use bigdecimal::BigDecimal;
+use starknet::core::types::Felt;
+
+use crate::services::rebalancer::RebalanceTarget;
+
+/// Whether a liquidation repays a position's debt in full or only the portion allowed by the
+/// protocol's liquidation factor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiquidationMode {
+    Full,
+    Partial,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub liquidation_mode: LiquidationMode,
+    pub liquidate_address: Felt,
+
+    /// Number of executors pulling candidates off the detector's channel, i.e. how many
+    /// liquidations can be built/submitted concurrently.
+    pub max_concurrent_liquidations: usize,
+    /// Capacity of the bounded channel between the detector and the executor pool.
+    pub liquidation_queue_size: usize,
+
+    /// Base backoff, in seconds, for a position/oracle asset that failed: after `n` consecutive
+    /// failures it is not retried for `error_backoff_base_secs * 2^n` seconds (capped below).
+    pub error_backoff_base_secs: u64,
+    /// Upper bound, in seconds, on the exponential backoff.
+    pub error_backoff_max_secs: u64,
+
+    /// Base URL of the DEX aggregator used to quote and build collateral-sale swaps.
+    pub dex_aggregator_url: String,
+    /// Maximum acceptable price impact (in bps) for a collateral-sale route; routes above this
+    /// are treated as "market not liquid".
+    pub max_price_impact_bps: u32,
+    /// Slippage tolerance (in bps) passed to the DEX aggregator when building swap calldata.
+    pub swap_slippage_bps: u32,
+    /// Whether to append the collateral-sale swap call to the liquidation tx itself.
+    pub atomic_collateral_sale: bool,
+
+    /// How often the `Rebalancer` runs, in seconds.
+    pub rebalance_interval: u64,
+    /// The asset every other balance is swapped back into.
+    pub rebalancer_base_asset: Felt,
+    /// Per-asset dust thresholds and minimum swap sizes for the `Rebalancer`.
+    pub rebalancer_targets: Vec<RebalanceTarget>,
+
+    /// Timeout, in seconds, for a single position's profitability/fee-estimation evaluation.
+    pub position_eval_timeout_secs: u64,
+
+    /// Minimum free balance (in the funding asset) an executor must leave after reserving a
+    /// liquidation's debt, to keep the account solvent across concurrent liquidations.
+    pub solvency_buffer: BigDecimal,
+}
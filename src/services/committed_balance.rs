@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bigdecimal::BigDecimal;
+use starknet::core::types::Felt;
+use tokio::sync::RwLock;
+
+/// Tracks, per funding asset, how much balance is currently reserved by in-flight liquidations
+/// that haven't settled on-chain yet. Lets concurrent executors assert ahead of submission that
+/// the account stays solvent instead of finding out from a reverted tx.
+#[derive(Clone, Default)]
+pub struct CommittedBalanceLedger {
+    committed: Arc<RwLock<HashMap<Felt, BigDecimal>>>,
+}
+
+impl CommittedBalanceLedger {
+    pub fn new() -> CommittedBalanceLedger {
+        CommittedBalanceLedger::default()
+    }
+
+    /// Reserves `amount` of `asset` against `available_balance` if, after accounting for what's
+    /// already committed, at least `buffer` would remain free. Returns `true` if the reservation
+    /// was made; the caller must `release` the same amount once the liquidation settles.
+    pub async fn try_reserve(
+        &self,
+        asset: Felt,
+        amount: BigDecimal,
+        available_balance: &BigDecimal,
+        buffer: &BigDecimal,
+    ) -> bool {
+        let mut committed = self.committed.write().await;
+        let already_committed = committed.get(&asset).cloned().unwrap_or_default();
+        let projected_free = available_balance - already_committed.clone() - amount.clone();
+        if projected_free < *buffer {
+            return false;
+        }
+        committed.insert(asset, already_committed + amount);
+        true
+    }
+
+    /// Releases a previously-reserved `amount` of `asset`, e.g. once its liquidation tx has been
+    /// accepted (or has failed to submit at all).
+    pub async fn release(&self, asset: Felt, amount: BigDecimal) {
+        let mut committed = self.committed.write().await;
+        if let Some(current) = committed.get_mut(&asset) {
+            *current -= amount;
+            if *current <= BigDecimal::from(0) {
+                committed.remove(&asset);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reserves_while_the_buffer_stays_free() {
+        let ledger = CommittedBalanceLedger::new();
+        let asset = Felt::from(1u32);
+        let available = BigDecimal::from(100);
+        let buffer = BigDecimal::from(10);
+
+        assert!(
+            ledger
+                .try_reserve(asset, BigDecimal::from(50), &available, &buffer)
+                .await
+        );
+        // A further reservation that would breach the buffer is refused.
+        assert!(
+            !ledger
+                .try_reserve(asset, BigDecimal::from(50), &available, &buffer)
+                .await
+        );
+        // But one that still leaves the buffer free goes through.
+        assert!(
+            ledger
+                .try_reserve(asset, BigDecimal::from(40), &available, &buffer)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn release_to_zero_removes_the_key() {
+        let ledger = CommittedBalanceLedger::new();
+        let asset = Felt::from(1u32);
+        let available = BigDecimal::from(100);
+        let buffer = BigDecimal::from(0);
+
+        assert!(
+            ledger
+                .try_reserve(asset, BigDecimal::from(30), &available, &buffer)
+                .await
+        );
+        assert!(ledger.committed.read().await.contains_key(&asset));
+
+        ledger.release(asset, BigDecimal::from(30)).await;
+        assert!(!ledger.committed.read().await.contains_key(&asset));
+    }
+
+    #[tokio::test]
+    async fn reservations_are_tracked_independently_per_asset() {
+        let ledger = CommittedBalanceLedger::new();
+        let asset_a = Felt::from(1u32);
+        let asset_b = Felt::from(2u32);
+        let available = BigDecimal::from(100);
+        let buffer = BigDecimal::from(10);
+
+        assert!(
+            ledger
+                .try_reserve(asset_a, BigDecimal::from(90), &available, &buffer)
+                .await
+        );
+        // Asset B's own balance is untouched by asset A's reservation.
+        assert!(
+            ledger
+                .try_reserve(asset_b, BigDecimal::from(90), &available, &buffer)
+                .await
+        );
+    }
+}
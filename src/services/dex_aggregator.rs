@@ -0,0 +1,154 @@
+use anyhow::{anyhow, Result};
+use bigdecimal::BigDecimal;
+use starknet::core::types::{Call, Felt};
+
+/// A quoted route to swap `sell_amount` of `sell_token` into `buy_token`, as returned by the
+/// configured DEX aggregator (AVNU / Ekubo). `price_impact_bps` and `calls` let the caller
+/// decide whether the route is liquid enough to act on, and to append the swap to a liquidation
+/// so selling the seized collateral happens atomically.
+#[derive(Debug, Clone)]
+pub struct SwapRoute {
+    pub amount_out: BigDecimal,
+    pub price_impact_bps: u32,
+    pub calls: Vec<Call>,
+}
+
+/// A source of ExactIn swap routes on Starknet. Implemented against AVNU today; Ekubo's router
+/// could be added behind the same trait.
+#[async_trait::async_trait]
+pub trait DexAggregator: Send + Sync {
+    /// Quotes selling `sell_amount` of `sell_token` into `buy_token`, for `taker_address`.
+    /// Returns an error if the aggregator has no route at all (illiquid pair).
+    async fn quote_exact_in(
+        &self,
+        sell_token: Felt,
+        buy_token: Felt,
+        sell_amount: BigDecimal,
+        taker_address: Felt,
+    ) -> Result<SwapRoute>;
+}
+
+/// Thin client for AVNU's swap API (https://starknet.api.avnu.fi).
+pub struct AvnuAggregator {
+    http: reqwest::Client,
+    base_url: String,
+    /// Slippage tolerance (in bps) passed to `/swap/v2/build`, from `Config::swap_slippage_bps`.
+    slippage_bps: u32,
+}
+
+impl AvnuAggregator {
+    pub fn new(base_url: String, slippage_bps: u32) -> AvnuAggregator {
+        AvnuAggregator {
+            http: reqwest::Client::new(),
+            base_url,
+            slippage_bps,
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct AvnuQuote {
+    #[serde(rename = "quoteId")]
+    quote_id: String,
+    #[serde(rename = "buyAmount")]
+    buy_amount: String,
+    #[serde(rename = "priceImpactBps")]
+    price_impact_bps: u32,
+}
+
+#[derive(serde::Serialize)]
+struct AvnuBuildRequest {
+    #[serde(rename = "quoteId")]
+    quote_id: String,
+    #[serde(rename = "takerAddress")]
+    taker_address: String,
+    #[serde(rename = "slippage")]
+    slippage: f64,
+}
+
+#[derive(serde::Deserialize)]
+struct AvnuCall {
+    #[serde(rename = "contractAddress")]
+    contract_address: String,
+    entrypoint: String,
+    calldata: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct AvnuBuildResponse {
+    calls: Vec<AvnuCall>,
+}
+
+impl TryFrom<AvnuCall> for Call {
+    type Error = anyhow::Error;
+
+    fn try_from(call: AvnuCall) -> Result<Call> {
+        Ok(Call {
+            to: Felt::from_hex(&call.contract_address)?,
+            selector: Felt::from_hex(&call.entrypoint)?,
+            calldata: call
+                .calldata
+                .iter()
+                .map(|felt| Felt::from_hex(felt))
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl DexAggregator for AvnuAggregator {
+    async fn quote_exact_in(
+        &self,
+        sell_token: Felt,
+        buy_token: Felt,
+        sell_amount: BigDecimal,
+        taker_address: Felt,
+    ) -> Result<SwapRoute> {
+        let response = self
+            .http
+            .get(format!("{}/swap/v2/quotes", self.base_url))
+            .query(&[
+                ("sellTokenAddress", sell_token.to_hex_string()),
+                ("buyTokenAddress", buy_token.to_hex_string()),
+                ("sellAmount", sell_amount.to_string()),
+                ("takerAddress", taker_address.to_hex_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(|e| anyhow!("⛔ No route found from the DEX aggregator: {e}"))?;
+
+        let quotes: Vec<AvnuQuote> = response.json().await?;
+        let best = quotes
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("⛔ DEX aggregator returned no quotes"))?;
+
+        // Turn the quote into executable calldata up-front: a route the caller can't actually
+        // submit isn't a usable route.
+        let build_response = self
+            .http
+            .post(format!("{}/swap/v2/build", self.base_url))
+            .json(&AvnuBuildRequest {
+                quote_id: best.quote_id,
+                taker_address: taker_address.to_hex_string(),
+                slippage: f64::from(self.slippage_bps) / 10_000.0,
+            })
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(|e| anyhow!("⛔ DEX aggregator couldn't build the swap calldata: {e}"))?;
+        let build: AvnuBuildResponse = build_response.json().await?;
+        let calls = build
+            .calls
+            .into_iter()
+            .map(Call::try_from)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(SwapRoute {
+            amount_out: best.buy_amount.parse()?,
+            price_impact_bps: best.price_impact_bps,
+            calls,
+        })
+    }
+}
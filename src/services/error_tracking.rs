@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use starknet::core::types::Felt;
+use tokio::sync::RwLock;
+
+use crate::config::Config;
+
+/// Consecutive-failure count and last-attempt timestamp for a single key (a position or an
+/// oracle asset), used to compute the exponential backoff before the next attempt.
+#[derive(Debug, Clone, Copy)]
+struct FailureRecord {
+    consecutive_failures: u32,
+    last_failure: Instant,
+}
+
+/// Tracks per-position and per-oracle-asset failures so the monitoring loop backs off from a
+/// position or a price feed that keeps failing instead of retrying it every
+/// `check_positions_interval`.
+#[derive(Clone)]
+pub struct ErrorTracking {
+    backoff_base: Duration,
+    max_backoff: Duration,
+    position_failures: Arc<RwLock<HashMap<u64, FailureRecord>>>,
+    oracle_failures: Arc<RwLock<HashMap<Felt, FailureRecord>>>,
+}
+
+impl ErrorTracking {
+    pub fn new(config: &Config) -> ErrorTracking {
+        ErrorTracking {
+            backoff_base: Duration::from_secs(config.error_backoff_base_secs),
+            max_backoff: Duration::from_secs(config.error_backoff_max_secs),
+            position_failures: Arc::new(RwLock::new(HashMap::new())),
+            oracle_failures: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Returns true while `position_key` is still within its backoff window and should not be
+    /// retried yet.
+    pub async fn should_skip_position(&self, position_key: u64) -> bool {
+        let failures = self.position_failures.read().await;
+        match failures.get(&position_key) {
+            Some(record) => !self.backoff_elapsed(record),
+            None => false,
+        }
+    }
+
+    /// Returns true while `asset`'s oracle price is still within its backoff window, so every
+    /// position depending on that asset is skipped together.
+    pub async fn should_skip_oracle(&self, asset: Felt) -> bool {
+        let failures = self.oracle_failures.read().await;
+        match failures.get(&asset) {
+            Some(record) => !self.backoff_elapsed(record),
+            None => false,
+        }
+    }
+
+    /// Records a failed liquidation attempt for `position_key`, bumping its backoff.
+    pub async fn record_position_failure(&self, position_key: u64) {
+        let mut failures = self.position_failures.write().await;
+        let record = failures.entry(position_key).or_insert(FailureRecord {
+            consecutive_failures: 0,
+            last_failure: Instant::now(),
+        });
+        record.consecutive_failures = record.consecutive_failures.saturating_add(1);
+        record.last_failure = Instant::now();
+    }
+
+    /// Clears the failure count for `position_key` after a successful attempt.
+    pub async fn record_position_success(&self, position_key: u64) {
+        self.position_failures.write().await.remove(&position_key);
+    }
+
+    /// Records a failed price lookup for `asset`, bumping its backoff.
+    pub async fn record_oracle_failure(&self, asset: Felt) {
+        let mut failures = self.oracle_failures.write().await;
+        let record = failures.entry(asset).or_insert(FailureRecord {
+            consecutive_failures: 0,
+            last_failure: Instant::now(),
+        });
+        record.consecutive_failures = record.consecutive_failures.saturating_add(1);
+        record.last_failure = Instant::now();
+    }
+
+    /// Clears the failure count for `asset` after a successful price lookup.
+    pub async fn record_oracle_success(&self, asset: Felt) {
+        self.oracle_failures.write().await.remove(&asset);
+    }
+
+    /// `true` once `backoff_base * 2^consecutive_failures` (capped at `max_backoff`) has passed
+    /// since `last_failure`.
+    fn backoff_elapsed(&self, record: &FailureRecord) -> bool {
+        let backoff = self
+            .backoff_base
+            .saturating_mul(1 << record.consecutive_failures.min(16))
+            .min(self.max_backoff);
+        record.last_failure.elapsed() >= backoff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracking(backoff_base: Duration, max_backoff: Duration) -> ErrorTracking {
+        ErrorTracking {
+            backoff_base,
+            max_backoff,
+            position_failures: Arc::new(RwLock::new(HashMap::new())),
+            oracle_failures: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    #[tokio::test]
+    async fn skips_a_position_until_its_backoff_elapses() {
+        let tracking = tracking(Duration::from_secs(10), Duration::from_secs(3600));
+        assert!(!tracking.should_skip_position(1).await);
+
+        tracking.record_position_failure(1).await;
+        assert!(tracking.should_skip_position(1).await);
+
+        // Backdate the last failure past the first-failure backoff window (10s * 2^1 = 20s).
+        tracking
+            .position_failures
+            .write()
+            .await
+            .get_mut(&1)
+            .unwrap()
+            .last_failure = Instant::now() - Duration::from_secs(21);
+        assert!(!tracking.should_skip_position(1).await);
+    }
+
+    #[tokio::test]
+    async fn success_resets_the_failure_count() {
+        let tracking = tracking(Duration::from_secs(10), Duration::from_secs(3600));
+        tracking.record_position_failure(1).await;
+        tracking.record_position_failure(1).await;
+        tracking.record_position_success(1).await;
+        assert!(!tracking.position_failures.read().await.contains_key(&1));
+    }
+
+    #[tokio::test]
+    async fn oracle_success_resets_the_failure_count() {
+        let tracking = tracking(Duration::from_secs(10), Duration::from_secs(3600));
+        let asset = Felt::from(1u32);
+        tracking.record_oracle_failure(asset).await;
+        assert!(tracking.should_skip_oracle(asset).await);
+
+        tracking.record_oracle_success(asset).await;
+        assert!(!tracking.oracle_failures.read().await.contains_key(&asset));
+        assert!(!tracking.should_skip_oracle(asset).await);
+    }
+
+    #[tokio::test]
+    async fn backoff_is_capped_at_max_backoff() {
+        let tracking = tracking(Duration::from_secs(10), Duration::from_secs(30));
+        let record = FailureRecord {
+            // 2^16 * 10s would vastly exceed max_backoff if it weren't capped.
+            consecutive_failures: 16,
+            last_failure: Instant::now() - Duration::from_secs(29),
+        };
+        assert!(!tracking.backoff_elapsed(&record));
+
+        let record = FailureRecord {
+            consecutive_failures: 16,
+            last_failure: Instant::now() - Duration::from_secs(31),
+        };
+        assert!(tracking.backoff_elapsed(&record));
+    }
+}
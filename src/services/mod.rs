@@ -0,0 +1,6 @@
+pub mod committed_balance;
+pub mod dex_aggregator;
+pub mod error_tracking;
+pub mod monitoring;
+pub mod oracle;
+pub mod rebalancer;
@@ -1,18 +1,26 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
 use anyhow::{anyhow, Result};
-use bigdecimal::num_bigint::BigInt;
 use bigdecimal::BigDecimal;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
 use starknet::{
     core::types::{Call, Felt},
     providers::{jsonrpc::HttpTransport, JsonRpcClient},
 };
 use tokio::sync::mpsc::UnboundedReceiver;
-use tokio::time::interval;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::{interval, timeout};
 
 use crate::{
     config::Config,
-    services::oracle::LatestOraclePrices,
+    services::{
+        committed_balance::CommittedBalanceLedger,
+        dex_aggregator::{AvnuAggregator, DexAggregator},
+        error_tracking::ErrorTracking,
+        oracle::LatestOraclePrices,
+        rebalancer::Rebalancer,
+    },
     storages::Storage,
     types::{
         account::StarknetAccount,
@@ -22,15 +30,38 @@ use crate::{
 };
 
 pub struct MonitoringService {
-    config: Config,
-    rpc_client: Arc<JsonRpcClient<HttpTransport>>,
-    account: StarknetAccount,
+    executor: Arc<Executor>,
+    executor_pool_size: usize,
     positions_receiver: UnboundedReceiver<(u64, Position)>,
     positions: PositionsMap,
-    latest_oracle_prices: LatestOraclePrices,
     storage: Box<dyn Storage>,
     check_positions_interval: Duration,
+    candidates_tx: async_channel::Sender<Position>,
+    candidates_rx: async_channel::Receiver<Position>,
+    rebalancer: Rebalancer,
+    rebalance_interval: Duration,
+}
+
+/// Everything a liquidation executor needs, shared (via `Arc`) between the long-lived executor
+/// pool and the detector running in `MonitoringService::start`'s select loop.
+struct Executor {
+    config: Config,
+    rpc_client: Arc<JsonRpcClient<HttpTransport>>,
+    account: StarknetAccount,
+    latest_oracle_prices: LatestOraclePrices,
     min_profit: BigDecimal,
+    error_tracking: ErrorTracking,
+    dex_aggregator: Arc<dyn DexAggregator>,
+    committed_balances: CommittedBalanceLedger,
+    position_eval_timeout: Duration,
+    // Serializes nonce assignment: `execute_txs` is called concurrently by every executor
+    // sharing this one `account`, and nothing else here guarantees concurrent submissions don't
+    // collide on the same nonce and revert after gas. Held only around the submission itself,
+    // not around waiting for acceptance, so in-flight txs can still be confirmed concurrently.
+    submission_lock: Mutex<()>,
+    // Keys of positions that are queued or currently being processed by the executor pool, so a
+    // later scan tick doesn't re-queue a position that's still in flight from an earlier one.
+    in_flight_positions: RwLock<HashSet<u64>>,
 }
 
 impl MonitoringService {
@@ -44,28 +75,77 @@ impl MonitoringService {
         check_positions_interval: u64,
         min_profit: BigDecimal,
     ) -> MonitoringService {
-        MonitoringService {
-            config,
-            rpc_client,
+        let dex_aggregator: Arc<dyn DexAggregator> =
+            Arc::new(AvnuAggregator::new(
+                config.dex_aggregator_url.clone(),
+                config.swap_slippage_bps,
+            ));
+        let rebalancer = Rebalancer::new(
+            &config,
+            rpc_client.clone(),
+            account.clone(),
+            latest_oracle_prices.clone(),
+            dex_aggregator.clone(),
+        );
+        let rebalance_interval = Duration::from_secs(config.rebalance_interval);
+        let executor_pool_size = config.max_concurrent_liquidations;
+        let (candidates_tx, candidates_rx) = async_channel::bounded(config.liquidation_queue_size);
+
+        let executor = Arc::new(Executor {
+            error_tracking: ErrorTracking::new(&config),
+            position_eval_timeout: Duration::from_secs(config.position_eval_timeout_secs),
+            committed_balances: CommittedBalanceLedger::new(),
+            submission_lock: Mutex::new(()),
+            in_flight_positions: RwLock::new(HashSet::new()),
+            dex_aggregator,
+            rpc_client: rpc_client.clone(),
             account,
+            latest_oracle_prices: latest_oracle_prices.clone(),
+            min_profit,
+            config,
+        });
+
+        MonitoringService {
+            executor,
+            executor_pool_size,
             positions_receiver,
             positions: PositionsMap::from_storage(storage.as_ref()),
-            latest_oracle_prices,
             storage,
             check_positions_interval: Duration::from_secs(check_positions_interval),
-            min_profit,
+            candidates_tx,
+            candidates_rx,
+            rebalancer,
+            rebalance_interval,
         }
     }
 
     /// Starts the monitoring service.
     pub async fn start(mut self) -> Result<()> {
         let mut update_interval = interval(self.check_positions_interval);
+        let mut rebalance_interval = interval(self.rebalance_interval);
+
+        // Spawn the executor pool once, for the lifetime of the service: each executor keeps
+        // draining `candidates_rx` across scan cycles instead of being rebuilt (and awaited to
+        // completion) every tick, so a slow in-flight liquidation never blocks the next scan, the
+        // rebalancer, or new positions coming in from the indexer.
+        for _ in 0..self.executor_pool_size {
+            let executor = self.executor.clone();
+            let candidates_rx = self.candidates_rx.clone();
+            tokio::spawn(Executor::run(executor, candidates_rx));
+        }
 
         loop {
             tokio::select! {
                 // Monitor the positions every N seconds
                 _ = update_interval.tick() => {
-                    self.monitor_positions_liquidability().await?;
+                    self.scan_for_liquidable_positions().await?;
+                }
+
+                // Sweep seized collateral back into the base asset every N seconds
+                _ = rebalance_interval.tick() => {
+                    if let Err(e) = self.rebalancer.rebalance().await {
+                        tracing::error!("[♻️ Rebalancer] Error rebalancing: {e:#}");
+                    }
                 }
 
                 // Insert the new positions indexed by the IndexerService
@@ -84,39 +164,149 @@ impl MonitoringService {
         }
     }
 
-    /// Update all monitored positions and check if it's worth to liquidate any.
+    /// Scans `positions` for liquidatable candidates and feeds them into the persistent
+    /// `candidates_tx` channel that the long-lived executor pool drains from. Positions are
+    /// shuffled before being scanned, so running several liquidator instances doesn't have them
+    /// all race for the same top position every cycle.
     /// TODO: Check issue for multicall update:
     /// https://github.com/astraly-labs/vesu-liquidator/issues/12
-    async fn monitor_positions_liquidability(&self) -> Result<()> {
+    async fn scan_for_liquidable_positions(&self) -> Result<()> {
         let monitored_positions = self.positions.0.read().await;
         if monitored_positions.is_empty() {
             return Ok(());
         }
+        let mut candidates: Vec<Position> = monitored_positions.values().cloned().collect();
+        drop(monitored_positions);
+        // Shuffle so concurrent liquidator instances don't all race for the same top position.
+        candidates.shuffle(&mut thread_rng());
+
         tracing::info!("[🔭 Monitoring] Checking if any position is liquidable...");
-        for (_, position) in monitored_positions.iter() {
-            if position.is_liquidable(&self.latest_oracle_prices).await {
+        for position in candidates {
+            if self.executor.error_tracking.should_skip_position(position.key()).await {
+                continue;
+            }
+            if self
+                .executor
+                .error_tracking
+                .should_skip_oracle(position.collateral.address)
+                .await
+                || self
+                    .executor
+                    .error_tracking
+                    .should_skip_oracle(position.debt.address)
+                    .await
+            {
+                continue;
+            }
+            if position.is_liquidable(&self.executor.latest_oracle_prices).await {
+                // A position already queued or being processed from an earlier scan shouldn't be
+                // queued again: the executor pool is now long-lived and persists across ticks, so
+                // a position can still be in flight when the next scan finds it liquidable again.
+                if !self
+                    .executor
+                    .in_flight_positions
+                    .write()
+                    .await
+                    .insert(position.key())
+                {
+                    continue;
+                }
                 tracing::info!(
                     "[🔭 Monitoring] Liquidatable position found #{}!",
                     position.key()
                 );
-                let _profit_made = self.try_to_liquidate_position(position).await?;
+                if self.candidates_tx.send(position).await.is_err() {
+                    // The executor pool is gone; nothing left to do.
+                    break;
+                }
             }
         }
         tracing::info!("[🔭 Monitoring] 🤨 They're good.. for now...");
         Ok(())
     }
+}
+
+impl Executor {
+    /// Long-lived loop pulling candidates off the shared, `Clone`-able receiver and trying to
+    /// liquidate each of them. `Config::max_concurrent_liquidations` of these run concurrently,
+    /// each with its own receiver handle (`async_channel::Receiver` is MPMC) so an idle executor
+    /// waits on the channel itself rather than serializing behind a shared lock.
+    async fn run(executor: Arc<Executor>, candidates_rx: async_channel::Receiver<Position>) {
+        while let Ok(position) = candidates_rx.recv().await {
+            let key = position.key();
+            if let Err(e) = executor.try_to_liquidate_position(&position).await {
+                tracing::error!("[🔭 Monitoring] Error liquidating position #{key}: {e:#}");
+            }
+            // Done (or given up) with this position: let it be picked up again on a later scan.
+            executor.in_flight_positions.write().await.remove(&key);
+        }
+    }
 
     /// Check if a position is liquidable, computes the profitability and if it's worth it
     /// liquidate it.
+    ///
+    /// Before submitting, reserves `debt_repaid` (the actual amount of debt the liquidation
+    /// repays, not the `0`-for-full-mode call sentinel) against the shared committed-balance
+    /// ledger for the funding asset: since several executors may run this concurrently, each
+    /// one's independently-computed profitability could otherwise commit more balance than the
+    /// account actually holds. The reservation is released once the tx has settled (or failed
+    /// to submit), regardless of outcome.
     async fn try_to_liquidate_position(&self, position: &Position) -> Result<BigDecimal> {
-        let (profit, txs) = self.compute_profitability(position).await?;
-        if profit >= self.min_profit {
+        let (profit, txs, funding_asset, debt_repaid) =
+            match self.compute_profitability(position).await {
+                Ok(result) => result,
+                Err(e) => {
+                    // Oracle-source failures are recorded per-asset at the point of the price
+                    // lookup (`require_oracle_price`), not guessed here from the error message.
+                    self.error_tracking.record_position_failure(position.key()).await;
+                    return Err(e);
+                }
+            };
+        if profit < self.min_profit {
             tracing::info!(
-                "[🔭 Monitoring] Trying to liquidate position for #{} {}!",
+                "[🔭 Monitoring] Position is not worth liquidating (estimated profit: {}, minimum required: {}), skipping...",
                 profit,
-                position.debt.name
+                self.min_profit
+            );
+            self.error_tracking.record_position_success(position.key()).await;
+            return Ok(profit);
+        }
+
+        let available_balance = self
+            .account
+            .balance_of(funding_asset, self.rpc_client.clone())
+            .await?;
+        if !self
+            .committed_balances
+            .try_reserve(
+                funding_asset,
+                debt_repaid.clone(),
+                &available_balance,
+                &self.config.solvency_buffer,
+            )
+            .await
+        {
+            tracing::warn!(
+                "[🔭 Monitoring] Skipping position #{} to stay solvent: reserving {} would breach the {} buffer",
+                position.key(),
+                debt_repaid,
+                self.config.solvency_buffer
             );
-            let tx_hash_felt = self.account.execute_txs(&txs).await?;
+            return Ok(BigDecimal::from(0));
+        }
+
+        tracing::info!(
+            "[🔭 Monitoring] Trying to liquidate position for #{} {}!",
+            profit,
+            position.debt.name
+        );
+        let liquidation_result = async {
+            let tx_hash_felt = {
+                // Only nonce assignment/submission needs to be serialized; holding the lock past
+                // this point would block other executors' submissions on this one's acceptance.
+                let _submission_guard = self.submission_lock.lock().await;
+                self.account.execute_txs(&txs).await?
+            };
             let tx_hash = tx_hash_felt.to_string();
             self.wait_for_tx_to_be_accepted(&tx_hash).await?;
             tracing::info!(
@@ -124,26 +314,69 @@ impl MonitoringService {
                 position.key(),
                 tx_hash
             );
-        } else {
-            tracing::info!(
-                "[🔭 Monitoring] Position is not worth liquidating (estimated profit: {}, minimum required: {}), skipping...",
-                profit,
-                self.min_profit
-            );
+            Ok::<(), anyhow::Error>(())
         }
+        .await;
+        self.committed_balances
+            .release(funding_asset, debt_repaid)
+            .await;
+        if liquidation_result.is_err() {
+            // A reverted/failed submission is exactly the kind of repeated failure the backoff
+            // is meant to catch, so it must count too, not just `compute_profitability` errors.
+            self.error_tracking.record_position_failure(position.key()).await;
+        }
+        liquidation_result?;
+
+        self.error_tracking.record_position_success(position.key()).await;
         Ok(profit)
     }
 
-    /// Simulates the profit generated by liquidating a given position. Returns the profit
-    /// and the transactions needed to liquidate the position.
-    async fn compute_profitability(&self, position: &Position) -> Result<(BigDecimal, Vec<Call>)> {
+    /// Bounds a single piece of position-evaluation work (an RPC call, an oracle/DEX query...)
+    /// by `Config::position_eval_timeout_secs`, so one hung request can't stall the whole scan cycle.
+    async fn with_eval_timeout<T>(&self, fut: impl std::future::Future<Output = T>) -> Result<T> {
+        timeout(self.position_eval_timeout, fut)
+            .await
+            .map_err(|_| anyhow!("⛔ Timed out after {:?}", self.position_eval_timeout))
+    }
+
+    /// Checks that the oracle actually has a price for `asset` before anything downstream relies
+    /// on it, recording the failure (or success) against that single asset (not every asset a
+    /// position happens to touch) so a stale feed only suppresses the positions that truly depend
+    /// on it, and a recovered feed actually clears its backoff.
+    async fn require_oracle_price(&self, asset: Felt) -> Result<()> {
+        let has_price = self.latest_oracle_prices.0.read().await.contains_key(&asset);
+        if !has_price {
+            self.error_tracking.record_oracle_failure(asset).await;
+            return Err(anyhow!("⛔ Oracle has no price for {:#x}", asset));
+        }
+        self.error_tracking.record_oracle_success(asset).await;
+        Ok(())
+    }
+
+    /// Simulates the profit generated by liquidating a given position. Returns the profit, the
+    /// transactions needed to liquidate the position, and the funding asset/amount that
+    /// `try_to_liquidate_position` must reserve against the committed-balance ledger before
+    /// submitting them.
+    ///
+    /// Rather than assuming a flat slippage on the seized collateral, this quotes a real
+    /// ExactIn route from the DEX aggregator for selling `min_collateral_to_receive` into the
+    /// debt asset, and uses its actual output/price impact. A route that doesn't exist or is too
+    /// impactful means the market can't absorb the sale, so the position is treated as not
+    /// liquidable right now.
+    async fn compute_profitability(
+        &self,
+        position: &Position,
+    ) -> Result<(BigDecimal, Vec<Call>, Felt, BigDecimal)> {
+        self.require_oracle_price(position.collateral.address).await?;
+        self.require_oracle_price(position.debt.address).await?;
+
         let (liquidable_amount_as_debt_asset, liquidable_amount_as_collateral_asset) = position
             .liquidable_amount(self.config.liquidation_mode, &self.latest_oracle_prices)
             .await?;
 
-        let liquidation_factor = position
-            .fetch_liquidation_factors(&self.config, self.rpc_client.clone())
-            .await;
+        let liquidation_factor = self
+            .with_eval_timeout(position.fetch_liquidation_factors(&self.config, self.rpc_client.clone()))
+            .await?;
 
         let debt_to_liquidate = match self.config.liquidation_mode {
             crate::config::LiquidationMode::Full => BigDecimal::from(0),
@@ -151,30 +384,68 @@ impl MonitoringService {
                 liquidable_amount_as_debt_asset.clone() * liquidation_factor.clone()
             }
         };
+        // `debt_to_liquidate` is the call argument (a `0` sentinel means "full" to the contract),
+        // not the actual amount repaid. Use the real repaid amount to net out the profit.
+        let debt_repaid = match self.config.liquidation_mode {
+            crate::config::LiquidationMode::Full => liquidable_amount_as_debt_asset.clone(),
+            crate::config::LiquidationMode::Partial => debt_to_liquidate.clone(),
+        };
         let min_collateral_to_receive =
             liquidable_amount_as_collateral_asset * liquidation_factor.clone();
-        let simulated_profit: BigDecimal =
-            liquidable_amount_as_debt_asset.clone() * (1 - liquidation_factor.clone());
-        let liquidation_txs = position
+
+        let route = self
+            .with_eval_timeout(self.dex_aggregator.quote_exact_in(
+                position.collateral.address,
+                position.debt.address,
+                min_collateral_to_receive.clone(),
+                self.account.address(),
+            ))
+            .await?
+            .map_err(|e| anyhow!("⛔ Market can't sell the seized collateral: {e}"))?;
+        if route.price_impact_bps > self.config.max_price_impact_bps {
+            return Err(anyhow!(
+                "⛔ Market can't sell the seized collateral: price impact {}bps exceeds the {}bps max",
+                route.price_impact_bps,
+                self.config.max_price_impact_bps
+            ));
+        }
+
+        let mut liquidation_txs = position
             .get_liquidation_txs(
                 &self.account,
                 self.config.liquidate_address,
-                debt_to_liquidate,
+                debt_to_liquidate.clone(),
                 min_collateral_to_receive,
             )
             .await?;
-        let execution_fees = self.account.estimate_fees_cost(&liquidation_txs).await?;
-        let slippage = BigDecimal::new(BigInt::from(5), 2);
-        let slippage_factor = BigDecimal::from(1) - slippage;
+        // Append the swap calls *before* estimating fees when they'll be submitted atomically, so
+        // the estimate reflects the gas cost of what's actually sent on-chain, not just the
+        // liquidation calls on their own.
+        if self.config.atomic_collateral_sale {
+            liquidation_txs.extend(route.calls);
+        }
+        let execution_fees = self
+            .with_eval_timeout(self.account.estimate_fees_cost(&liquidation_txs))
+            .await??;
+        // `route.amount_out` and `debt_repaid` are both denominated in the debt asset (the route
+        // sells collateral *into* it). `estimate_fees_cost` is assumed to already convert gas
+        // cost into that same unit, matching the baseline's discount calculation, which also
+        // subtracted fees from a debt-denominated figure.
+        let simulated_profit = route.amount_out - debt_repaid.clone() - execution_fees;
 
         Ok((
-            (simulated_profit * slippage_factor) - execution_fees,
+            simulated_profit,
             liquidation_txs,
+            position.debt.address,
+            // Reserve against the actual debt being repaid, not `debt_to_liquidate`'s `0`
+            // sentinel for `LiquidationMode::Full` — otherwise full liquidations, which commit
+            // the most balance, would always reserve zero.
+            debt_repaid,
         ))
     }
 
     /// Waits for a TX to be accepted on-chain.
-    pub async fn wait_for_tx_to_be_accepted(&self, tx_hash: &str) -> Result<()> {
+    async fn wait_for_tx_to_be_accepted(&self, tx_hash: &str) -> Result<()> {
         let tx_hash = Felt::from_hex(tx_hash)?;
         wait_for_tx(tx_hash, self.rpc_client.clone()).await?;
         Ok(())
@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use bigdecimal::BigDecimal;
+use starknet::{
+    core::types::Felt,
+    providers::{jsonrpc::HttpTransport, JsonRpcClient},
+};
+
+use crate::{
+    config::Config,
+    services::dex_aggregator::DexAggregator,
+    services::oracle::LatestOraclePrices,
+    types::account::StarknetAccount,
+};
+
+/// A single asset's rebalancing policy: how much of it we're willing to hold (`dust_threshold`)
+/// before sweeping the rest back into the base asset, and the smallest swap worth paying gas for.
+#[derive(Debug, Clone)]
+pub struct RebalanceTarget {
+    pub asset: Felt,
+    pub dust_threshold: BigDecimal,
+    pub min_swap_size: BigDecimal,
+}
+
+/// Converts collateral seized from liquidations back into the liquidator's base/funding asset,
+/// so its inventory doesn't drift away from what it needs to fund new liquidations. Driven on
+/// its own interval from `MonitoringService::start`.
+pub struct Rebalancer {
+    rpc_client: Arc<JsonRpcClient<HttpTransport>>,
+    account: StarknetAccount,
+    latest_oracle_prices: LatestOraclePrices,
+    dex_aggregator: Arc<dyn DexAggregator>,
+    base_asset: Felt,
+    targets: Vec<RebalanceTarget>,
+    max_price_impact_bps: u32,
+}
+
+impl Rebalancer {
+    pub fn new(
+        config: &Config,
+        rpc_client: Arc<JsonRpcClient<HttpTransport>>,
+        account: StarknetAccount,
+        latest_oracle_prices: LatestOraclePrices,
+        dex_aggregator: Arc<dyn DexAggregator>,
+    ) -> Rebalancer {
+        Rebalancer {
+            rpc_client,
+            account,
+            latest_oracle_prices,
+            dex_aggregator,
+            base_asset: config.rebalancer_base_asset,
+            targets: config.rebalancer_targets.clone(),
+            max_price_impact_bps: config.max_price_impact_bps,
+        }
+    }
+
+    /// Oracle-implied minimum acceptable output for selling `sell_amount` of `sell_asset` into
+    /// the base asset, tolerating at most `max_price_impact_bps` of slippage off the oracle
+    /// price (the same limit `compute_profitability` applies to a liquidation route's reported
+    /// price impact), so seized collateral can't be dumped into a thin or manipulated route at
+    /// an arbitrary price.
+    async fn min_acceptable_out(&self, sell_asset: Felt, sell_amount: &BigDecimal) -> Result<BigDecimal> {
+        let prices = self.latest_oracle_prices.0.read().await;
+        let sell_price = prices
+            .get(&sell_asset)
+            .cloned()
+            .ok_or_else(|| anyhow!("⛔ Oracle has no price for {:#x}", sell_asset))?;
+        let base_price = prices
+            .get(&self.base_asset)
+            .cloned()
+            .ok_or_else(|| anyhow!("⛔ Oracle has no price for {:#x}", self.base_asset))?;
+        drop(prices);
+        let expected_out = sell_amount * sell_price / base_price;
+        let tolerance = BigDecimal::from(self.max_price_impact_bps) / BigDecimal::from(10_000);
+        Ok(expected_out * (BigDecimal::from(1) - tolerance))
+    }
+
+    /// Reads the account's balance of every configured target asset and swaps whatever is above
+    /// its dust threshold back into the base asset.
+    pub async fn rebalance(&self) -> Result<()> {
+        for target in &self.targets {
+            if target.asset == self.base_asset {
+                continue;
+            }
+            let balance = self
+                .account
+                .balance_of(target.asset, self.rpc_client.clone())
+                .await?;
+            if balance <= target.dust_threshold {
+                continue;
+            }
+            let swap_amount = balance - target.dust_threshold.clone();
+            if swap_amount < target.min_swap_size {
+                continue;
+            }
+            tracing::info!(
+                "[♻️ Rebalancer] Swapping {} of {:#x} back into the base asset...",
+                swap_amount,
+                target.asset
+            );
+            let route = self
+                .dex_aggregator
+                .quote_exact_in(
+                    target.asset,
+                    self.base_asset,
+                    swap_amount.clone(),
+                    self.account.address(),
+                )
+                .await?;
+            let min_acceptable_out = self.min_acceptable_out(target.asset, &swap_amount).await?;
+            if route.amount_out < min_acceptable_out {
+                tracing::warn!(
+                    "[♻️ Rebalancer] Skipping swap of {:#x}: route output {} is below the oracle-implied minimum {}",
+                    target.asset,
+                    route.amount_out,
+                    min_acceptable_out
+                );
+                continue;
+            }
+            self.account.execute_txs(&route.calls).await?;
+        }
+        Ok(())
+    }
+}